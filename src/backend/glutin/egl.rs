@@ -0,0 +1,88 @@
+#![cfg(all(feature = "glutin", target_os = "linux"))]
+/*!
+
+Zero-copy import of externally-created EGL images as glium textures.
+
+This is intended for compositor-style workflows, where client buffers arrive as `EGLImage`s
+created by another process (for example a Wayland client's buffer, imported by a compositor)
+and need to be displayed without a CPU-side copy.
+
+Only available on Linux, since `EGLImageKHR` and the `GL_OES_EGL_image` extension are an
+EGL/GLES concept that doesn't apply to the WGL/CGL backends glutin uses elsewhere.
+
+Only binding the image to `GL_TEXTURE_2D` is supported. Binding to `GL_TEXTURE_EXTERNAL_OES`
+(needed for some YUV/hardware-decoded formats) would require returning a type that tracks that
+target and never rebinds the name as `GL_TEXTURE_2D`, and a `samplerExternalOES`-compatible
+uniform type for shaders to sample it with, neither of which glium currently has; support for
+that is left for a follow-up.
+
+*/
+use std::mem;
+use std::os::raw::c_void;
+
+use texture::{Texture2d, TextureCreationError, UncompressedFloatFormat};
+use backend::{Context, Facade};
+
+/// An opaque handle to an `EGLImageKHR`, as created by `eglCreateImageKHR` (or an equivalent
+/// compositor API) in another process or library. Ownership of the image itself stays with
+/// whoever created it; importing it does not consume or destroy it.
+pub type EGLImageKHR = *const c_void;
+
+const GL_TEXTURE_2D: u32 = 0x0DE1;
+
+type GlGenTextures = unsafe extern "system" fn(n: i32, textures: *mut u32);
+type GlBindTexture = unsafe extern "system" fn(target: u32, texture: u32);
+type GlDeleteTextures = unsafe extern "system" fn(n: i32, textures: *const u32);
+type GlEglImageTargetTexture2dOes = unsafe extern "system" fn(target: u32, image: *const c_void);
+
+/// Imports an externally-created `EGLImage` as a glium texture, binding it to a freshly
+/// allocated `GL_TEXTURE_2D` name via `glEGLImageTargetTexture2DOES` rather than copying pixels.
+///
+/// `format` must describe the image's actual pixel layout (e.g. BGRA8 buffers are common from
+/// compositor clients and are not the same as RGBA8); glium has no way to inspect an opaque
+/// `EGLImageKHR` to determine this itself, so passing the wrong format will sample the texture
+/// with the wrong channel order or component type.
+///
+/// `facade` must be backed by a context exposing the `GL_OES_EGL_image` extension (as glutin's
+/// EGL-based contexts do); the required entry points are looked up through
+/// `Backend::get_proc_address`, and a missing extension is reported as
+/// `TextureCreationError::FormatNotSupported` rather than panicking. The returned texture owns
+/// the freshly allocated GL texture name and deletes it on drop; `image` itself is left
+/// untouched, since it is owned by whoever created it.
+pub unsafe fn import_egl_image_2d<F: ?Sized + Facade>(
+    facade: &F,
+    image: EGLImageKHR,
+    format: UncompressedFloatFormat,
+    dimensions: (u32, u32),
+) -> Result<Texture2d, TextureCreationError> {
+    let ctxt = facade.get_context();
+
+    let gen_textures: GlGenTextures = mem::transmute(try!(load_proc(ctxt, "glGenTextures")));
+    let bind_texture: GlBindTexture = mem::transmute(try!(load_proc(ctxt, "glBindTexture")));
+    let delete_textures: GlDeleteTextures = mem::transmute(try!(load_proc(ctxt, "glDeleteTextures")));
+    let image_target_texture_2d: GlEglImageTargetTexture2dOes =
+        mem::transmute(try!(load_proc(ctxt, "glEGLImageTargetTexture2DOES")));
+
+    let mut texture_id = 0;
+    gen_textures(1, &mut texture_id);
+    bind_texture(GL_TEXTURE_2D, texture_id);
+    image_target_texture_2d(GL_TEXTURE_2D, image);
+
+    match Texture2d::from_id(facade, format, texture_id, dimensions, true) {
+        Ok(texture) => Ok(texture),
+        Err(err) => {
+            delete_textures(1, &texture_id);
+            Err(err)
+        },
+    }
+}
+
+/// Looks up a required GL entry point, reporting it as a texture creation failure instead of
+/// panicking if the driver doesn't expose it (e.g. `import_egl_image_2d` called against a
+/// non-EGL/desktop-GL context).
+unsafe fn load_proc(ctxt: &Context, symbol: &str) -> Result<*const c_void, TextureCreationError> {
+    match ctxt.get_proc_address(symbol) {
+        addr if addr.is_null() => Err(TextureCreationError::FormatNotSupported),
+        addr => Ok(addr),
+    }
+}
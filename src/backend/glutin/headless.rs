@@ -0,0 +1,129 @@
+/*!
+
+Headless backend implementation for the glutin library.
+
+*/
+use std::cell::RefCell;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use super::glutin::{self, ContextCurrentState, PossiblyCurrent};
+use super::takeable_option::Takeable;
+use super::GlutinBackend;
+
+use {Frame, IncompatibleOpenGl};
+use debug;
+use context;
+use backend;
+use backend::Context;
+
+/// A GL context combined with a facade for drawing upon, without an associated window.
+///
+/// Useful for offscreen rendering, for example in tests or on a server.
+#[derive(Clone)]
+pub struct Headless {
+    // contains everything related to the current context and its state
+    context: Rc<context::Context>,
+    // kept alive so that the underlying glutin context outlives the facade
+    gl_context: Rc<RefCell<Takeable<glutin::Context<PossiblyCurrent>>>>,
+}
+
+impl Headless {
+    /// Create a new headless `glium` context from the given glutin context.
+    ///
+    /// Performs a compatibility check to make sure that all core elements of glium are supported
+    /// by the implementation.
+    pub fn new<T: ContextCurrentState>(
+        context: glutin::Context<T>,
+    ) -> Result<Self, IncompatibleOpenGl> {
+        Self::with_debug(context, Default::default())
+    }
+
+    /// Create a new headless `glium` context.
+    ///
+    /// This function does the same as `new`, except that the resulting context will assume that
+    /// the current OpenGL context will never change.
+    pub unsafe fn unchecked<T: ContextCurrentState>(
+        context: glutin::Context<T>,
+    ) -> Result<Self, IncompatibleOpenGl> {
+        Self::unchecked_with_debug(context, Default::default())
+    }
+
+    /// The same as the `new` constructor, but allows for specifying debug callback behaviour.
+    pub fn with_debug<T: ContextCurrentState>(
+        context: glutin::Context<T>,
+        debug: debug::DebugCallbackBehavior,
+    ) -> Result<Self, IncompatibleOpenGl> {
+        Self::new_inner(context, debug, true)
+    }
+
+    /// The same as the `unchecked` constructor, but allows for specifying debug callback behaviour.
+    pub unsafe fn unchecked_with_debug<T: ContextCurrentState>(
+        context: glutin::Context<T>,
+        debug: debug::DebugCallbackBehavior,
+    ) -> Result<Self, IncompatibleOpenGl> {
+        Self::new_inner(context, debug, false)
+    }
+
+    fn new_inner<T: ContextCurrentState>(
+        context: glutin::Context<T>,
+        debug: debug::DebugCallbackBehavior,
+        checked: bool,
+    ) -> Result<Self, IncompatibleOpenGl> {
+        let context = unsafe {
+            match context.make_current() {
+                Ok(context) => context,
+                Err((_, err)) => panic!("could not make the headless context current: {:?}", err),
+            }
+        };
+
+        // A headless context created without an OS window (e.g. a surfaceless or pbuffer-backed
+        // EGL context) has no inner size to query. In that case we can't know the intended
+        // render target size up front, so fall back to a dummy non-zero size rather than
+        // panicking; callers are expected to size their own render targets (FBOs) in that case.
+        let dimensions = {
+            let window = context.window();
+            match window.get_inner_size() {
+                Some(size) => {
+                    let hidpi_factor = window.get_hidpi_factor();
+                    let (width, height): (u32, u32) = size.into();
+                    ((width as f64 * hidpi_factor) as u32, (height as f64 * hidpi_factor) as u32)
+                },
+                None => (1, 1),
+            }
+        };
+
+        let gl_context = Rc::new(RefCell::new(Takeable::new(context)));
+        let backend = GlutinBackend { context: gl_context.clone(), headless_size: Some(dimensions) };
+        let context = try!(unsafe { context::Context::new(backend, checked, debug) });
+        Ok(Headless {
+            context: context,
+            gl_context: gl_context,
+        })
+    }
+
+    /// Start drawing on the backbuffer.
+    ///
+    /// This function returns a `Frame`, which can be used to draw on it. Since a headless
+    /// context has no swap chain, destroying the `Frame` is a no-op rather than a buffer swap.
+    #[inline]
+    pub fn draw(&self) -> Frame {
+        let (w, h) = self.get_framebuffer_dimensions();
+        Frame::new(self.context.clone(), (w, h))
+    }
+}
+
+impl Deref for Headless {
+    type Target = Context;
+    #[inline]
+    fn deref(&self) -> &Context {
+        &self.context
+    }
+}
+
+impl backend::Facade for Headless {
+    #[inline]
+    fn get_context(&self) -> &Rc<Context> {
+        &self.context
+    }
+}
@@ -9,8 +9,13 @@ Only available if the 'glutin' feature is enabled.
 
 */
 pub extern crate glutin;
+extern crate takeable_option;
 
 pub mod headless;
+pub mod egl;
+
+use self::glutin::{NotCurrent, PossiblyCurrent};
+use self::takeable_option::Takeable;
 
 use {Frame, IncompatibleOpenGl, SwapBuffersError};
 use debug;
@@ -18,7 +23,6 @@ use context;
 use backend;
 use backend::Context;
 use backend::Backend;
-use std;
 use std::cell::{Cell, RefCell, Ref};
 use std::error::Error;
 use std::fmt;
@@ -35,6 +39,10 @@ use std::os::raw::c_void;
 pub struct Display {
     // contains everything related to the current context and its state
     context: Rc<context::Context>,
+    // The glutin window and context, kept alive so that `rebuild` and friends can recreate it.
+    // `None` when the `Display` was built around an externally-owned context (see
+    // `from_current`), in which case glium does not own a window to rebuild.
+    gl_window: Option<Rc<RefCell<Takeable<glutin::Context<PossiblyCurrent>>>>>,
     // Used to check whether the framebuffer dimensions have changed between frames. If they have,
     // the glutin context must be resized accordingly.
     last_framebuffer_dimensions: Cell<(u32, u32)>,
@@ -45,30 +53,86 @@ pub struct Display {
 pub enum DisplayCreationError {
     /// An error has happened while creating the backend.
     GlutinCreationError(glutin::CreationError),
+    /// An error has happened while making the newly created context current.
+    ContextError(glutin::ContextError),
     /// The OpenGL implementation is too old.
     IncompatibleOpenGl(IncompatibleOpenGl),
 }
 
-struct NullBacked;
+/// A glutin surface whose underlying framebuffer can be resized to track the window it is
+/// attached to.
+///
+/// This is implemented for `glutin::Context<PossiblyCurrent>`, the type that both `Display` and
+/// `headless::Headless` hold. Only `Display::draw` calls it, and only on the window the `Display`
+/// owns; `Display`s built with `from_current` and `Headless` contexts are never resized.
+pub trait ResizeableSurface {
+    /// Resizes the surface to the given physical size, in pixels.
+    fn resize(&self, size: glutin::dpi::PhysicalSize);
+}
 
-unsafe impl backend::Backend for NullBacked {
+impl ResizeableSurface for glutin::Context<PossiblyCurrent> {
+    #[inline]
+    fn resize(&self, size: glutin::dpi::PhysicalSize) {
+        self.resize(size)
+    }
+}
+
+/// An implementation of the `Backend` trait for a glutin `Context`.
+///
+/// `headless_size`, when set, means this backend has no resizable OS window to query or swap:
+/// `swap_buffers` becomes a no-op and `get_framebuffer_dimensions` returns the fixed size the
+/// headless context was created with instead of querying a window that may not exist.
+pub(crate) struct GlutinBackend {
+    pub context: Rc<RefCell<Takeable<glutin::Context<PossiblyCurrent>>>>,
+    pub headless_size: Option<(u32, u32)>,
+}
+
+unsafe impl backend::Backend for GlutinBackend {
     fn swap_buffers(&self) -> Result<(), SwapBuffersError> {
-        Ok(())
+        if self.headless_size.is_some() {
+            return Ok(());
+        }
+
+        match self.context.borrow().swap_buffers() {
+            Ok(()) => Ok(()),
+            Err(glutin::ContextError::ContextLost) => Err(SwapBuffersError::ContextLost),
+            Err(glutin::ContextError::IoError(_)) |
+            Err(glutin::ContextError::OsError(_)) |
+            Err(glutin::ContextError::FunctionUnavailable) => Err(SwapBuffersError::AlreadySwapped),
+        }
     }
 
     unsafe fn get_proc_address(&self, symbol: &str) -> *const c_void {
-        std::ptr::null()
+        self.context.borrow().get_proc_address(symbol) as *const _
     }
 
     fn get_framebuffer_dimensions(&self) -> (u32, u32) {
-        (800, 600)
+        if let Some(size) = self.headless_size {
+            return size;
+        }
+
+        let gl_window = self.context.borrow();
+        let window = gl_window.window();
+        let (width, height) = window.get_inner_size().expect("glutin window doesn't exist anymore")
+            .into();
+        let hidpi_factor = window.get_hidpi_factor();
+        ((width as f64 * hidpi_factor) as u32, (height as f64 * hidpi_factor) as u32)
     }
 
     fn is_current(&self) -> bool {
-        true
+        self.context.borrow().is_current()
     }
 
     unsafe fn make_current(&self) {
+        let mut gl_window = self.context.borrow_mut();
+        let taken = Takeable::take(&mut gl_window);
+        match taken.make_current() {
+            Ok(ctxt) => Takeable::insert(&mut gl_window, ctxt),
+            Err((ctxt, err)) => {
+                Takeable::insert(&mut gl_window, ctxt);
+                panic!("glutin context lost while making current: {:?}", err);
+            },
+        }
     }
 }
 
@@ -77,52 +141,100 @@ impl Display {
     ///
     /// Performs a compatibility check to make sure that all core elements of glium are supported
     /// by the implementation.
-    pub fn new() -> Result<Self, DisplayCreationError>
+    pub fn new<'a>(
+        window_builder: glutin::WindowBuilder,
+        context_builder: glutin::ContextBuilder<'a, NotCurrent>,
+        events_loop: &glutin::EventsLoop,
+    ) -> Result<Self, DisplayCreationError>
     {
-        Self::from_gl_window().map_err(From::from)
-    }
-
-    /// Create a new glium `Display`.
-    ///
-    /// Performs a compatibility check to make sure that all core elements of glium are supported
-    /// by the implementation.
-    pub fn from_gl_window() -> Result<Self, IncompatibleOpenGl> {
-        Self::with_debug(Default::default())
+        Self::with_debug(window_builder, context_builder, events_loop, Default::default())
     }
 
     /// Create a new glium `Display`.
     ///
-    /// This function does the same as `build_glium`, except that the resulting context
+    /// This function does the same as `new`, except that the resulting context
     /// will assume that the current OpenGL context will never change.
-    pub unsafe fn unchecked() -> Result<Self, IncompatibleOpenGl> {
-        Self::unchecked_with_debug(Default::default())
+    pub unsafe fn unchecked<'a>(
+        window_builder: glutin::WindowBuilder,
+        context_builder: glutin::ContextBuilder<'a, NotCurrent>,
+        events_loop: &glutin::EventsLoop,
+    ) -> Result<Self, DisplayCreationError>
+    {
+        Self::unchecked_with_debug(window_builder, context_builder, events_loop, Default::default())
     }
 
     /// The same as the `new` constructor, but allows for specifying debug callback behaviour.
-    pub fn with_debug(debug: debug::DebugCallbackBehavior)
-        -> Result<Self, IncompatibleOpenGl>
+    pub fn with_debug<'a>(
+        window_builder: glutin::WindowBuilder,
+        context_builder: glutin::ContextBuilder<'a, NotCurrent>,
+        events_loop: &glutin::EventsLoop,
+        debug: debug::DebugCallbackBehavior,
+    ) -> Result<Self, DisplayCreationError>
     {
-        Self::new_inner(debug, true)
+        Self::new_inner(window_builder, context_builder, events_loop, debug, true)
     }
 
     /// The same as the `unchecked` constructor, but allows for specifying debug callback behaviour.
-    pub unsafe fn unchecked_with_debug(
+    pub unsafe fn unchecked_with_debug<'a>(
+        window_builder: glutin::WindowBuilder,
+        context_builder: glutin::ContextBuilder<'a, NotCurrent>,
+        events_loop: &glutin::EventsLoop,
         debug: debug::DebugCallbackBehavior,
-    ) -> Result<Self, IncompatibleOpenGl>
+    ) -> Result<Self, DisplayCreationError>
+    {
+        Self::new_inner(window_builder, context_builder, events_loop, debug, false)
+    }
+
+    fn new_inner<'a>(
+        window_builder: glutin::WindowBuilder,
+        context_builder: glutin::ContextBuilder<'a, NotCurrent>,
+        events_loop: &glutin::EventsLoop,
+        debug: debug::DebugCallbackBehavior,
+        checked: bool,
+    ) -> Result<Self, DisplayCreationError>
     {
-        Self::new_inner(debug, false)
+        let gl_window = try!(context_builder.build(window_builder, events_loop));
+        let gl_window = unsafe { try!(gl_window.make_current().map_err(|(_, err)| err)) };
+        let gl_window = Rc::new(RefCell::new(Takeable::new(gl_window)));
+
+        let glutin_backend = GlutinBackend { context: gl_window.clone(), headless_size: None };
+        Self::from_backend(glutin_backend, Some(gl_window), checked, debug).map_err(From::from)
     }
 
-    fn new_inner(
+    /// Creates a `Display` around a GL context that was created and made current by the caller,
+    /// rather than by glium itself.
+    ///
+    /// This is intended for embedding glium inside a toolkit that owns the window and GL context
+    /// (for example a GTK4 `GLArea`, an SDL2 window, or any surface obtained through
+    /// `raw-window-handle`). The caller is responsible for creating `backend` and making its
+    /// context current before calling this function; glium only runs the compatibility check and
+    /// builds its own `context::Context` around it. Because glium does not own the window in this
+    /// case, `rebuild` cannot be used on the resulting `Display`.
+    pub unsafe fn from_current<B>(
+        backend: B,
+        checked: bool,
         debug: debug::DebugCallbackBehavior,
+    ) -> Result<Self, IncompatibleOpenGl>
+    where
+        B: Backend + 'static,
+    {
+        Self::from_backend(backend, None, checked, debug)
+    }
+
+    fn from_backend<B>(
+        backend: B,
+        gl_window: Option<Rc<RefCell<Takeable<glutin::Context<PossiblyCurrent>>>>>,
         checked: bool,
+        debug: debug::DebugCallbackBehavior,
     ) -> Result<Self, IncompatibleOpenGl>
+    where
+        B: Backend + 'static,
     {
-        let glutin_backend = NullBacked {};
-        let framebuffer_dimensions = glutin_backend.get_framebuffer_dimensions();
-        let context = try!(unsafe { context::Context::new(glutin_backend, checked, debug) });
+        let framebuffer_dimensions = backend.get_framebuffer_dimensions();
+        let context = try!(unsafe { context::Context::new(backend, checked, debug) });
         Ok(Display {
             context: context,
+            gl_window: gl_window,
             last_framebuffer_dimensions: Cell::new(framebuffer_dimensions),
         })
     }
@@ -131,13 +243,33 @@ impl Display {
     ///
     /// This method ensures that the new `GlWindow`'s `Context` will share the display lists of the
     /// original `GlWindow`'s `Context`.
-    pub fn rebuild(
+    ///
+    /// # Panics
+    ///
+    /// Panics if this `Display` was built from an externally-owned context via `from_current`,
+    /// since glium does not own a window to rebuild in that case.
+    pub fn rebuild<'a>(
         &self,
         window_builder: glutin::WindowBuilder,
-        context_builder: glutin::ContextBuilder,
+        context_builder: glutin::ContextBuilder<'a, NotCurrent>,
         events_loop: &glutin::EventsLoop,
     ) -> Result<(), DisplayCreationError>
     {
+        let gl_window = self.gl_window.as_ref().unwrap_or_else(|| panic!(
+            "`rebuild` can only be called on a `Display` that owns its window; `Display`s built \
+             from an externally-owned context via `from_current` do not"
+        ));
+
+        let new_context = {
+            let old_context = gl_window.borrow();
+            try!(context_builder.with_shared_lists(&**old_context).build(window_builder, events_loop))
+        };
+        let new_context = unsafe { try!(new_context.make_current().map_err(|(_, err)| err)) };
+
+        // Dropping the old `Takeable` here destroys the old window and context.
+        *gl_window.borrow_mut() = Takeable::new(new_context);
+
+        self.last_framebuffer_dimensions.set(self.get_framebuffer_dimensions());
         Ok(())
     }
 
@@ -153,8 +285,30 @@ impl Display {
     #[inline]
     pub fn draw(&self) -> Frame {
         let (w, h) = self.get_framebuffer_dimensions();
+
+        if (w, h) != self.last_framebuffer_dimensions.get() {
+            if let Some(ref gl_window) = self.gl_window {
+                gl_window.borrow().resize(glutin::dpi::PhysicalSize::new(w as f64, h as f64));
+            }
+            self.last_framebuffer_dimensions.set((w, h));
+        }
+
         Frame::new(self.context.clone(), (w, h))
     }
+
+    /// Imports an externally-created EGL image as a glium texture, without copying its pixels.
+    ///
+    /// See [`egl::import_egl_image_2d`](egl/fn.import_egl_image_2d.html) for the requirements
+    /// on `image` and the ownership semantics of the returned texture. Only available on Linux.
+    #[cfg(target_os = "linux")]
+    pub unsafe fn import_egl_image_2d(
+        &self,
+        image: egl::EGLImageKHR,
+        format: ::texture::UncompressedFloatFormat,
+        dimensions: (u32, u32),
+    ) -> Result<::texture::Texture2d, ::texture::TextureCreationError> {
+        egl::import_egl_image_2d(self, image, format, dimensions)
+    }
 }
 
 impl fmt::Display for DisplayCreationError {
@@ -168,6 +322,7 @@ impl Error for DisplayCreationError {
     fn description(&self) -> &str {
         match *self {
             DisplayCreationError::GlutinCreationError(ref err) => err.description(),
+            DisplayCreationError::ContextError(ref err) => err.description(),
             DisplayCreationError::IncompatibleOpenGl(ref err) => err.description(),
         }
     }
@@ -176,6 +331,7 @@ impl Error for DisplayCreationError {
     fn cause(&self) -> Option<&Error> {
         match *self {
             DisplayCreationError::GlutinCreationError(ref err) => Some(err),
+            DisplayCreationError::ContextError(ref err) => Some(err),
             DisplayCreationError::IncompatibleOpenGl(ref err) => Some(err),
         }
     }
@@ -188,6 +344,13 @@ impl From<glutin::CreationError> for DisplayCreationError {
     }
 }
 
+impl From<glutin::ContextError> for DisplayCreationError {
+    #[inline]
+    fn from(err: glutin::ContextError) -> DisplayCreationError {
+        DisplayCreationError::ContextError(err)
+    }
+}
+
 impl From<IncompatibleOpenGl> for DisplayCreationError {
     #[inline]
     fn from(err: IncompatibleOpenGl) -> DisplayCreationError {